@@ -0,0 +1,571 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use errno::{set_errno, Errno};
+use s2n_tls::{
+    config::Config,
+    connection::Connection,
+    enums::Mode,
+    error::{Error as S2NError, ErrorType},
+};
+use std::{
+    error,
+    fmt,
+    future::Future,
+    io,
+    os::raw::{c_int, c_void},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// The default handshake timeout applied by [`TlsConnector`] and [`TlsAcceptor`].
+///
+/// A peer that starts a connection but never finishes negotiation would
+/// otherwise pin the handshake task forever, so a bound is applied unless one
+/// is explicitly chosen with `with_handshake_timeout`.
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Errors returned while negotiating a [`TlsStream`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum Error {
+    /// The handshake did not complete before the configured timeout elapsed.
+    HandshakeTimeout,
+    /// The transport failed to connect (DNS resolution, connection refused, …).
+    Io(io::Error),
+    /// The s2n-tls library or bindings reported an error.
+    Protocol(S2NError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::HandshakeTimeout => f.write_str("the TLS handshake timed out"),
+            Error::Io(err) => err.fmt(f),
+            Error::Protocol(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::HandshakeTimeout => None,
+            Error::Io(err) => Some(err),
+            Error::Protocol(err) => Some(err),
+        }
+    }
+}
+
+impl From<S2NError> for Error {
+    fn from(err: S2NError) -> Self {
+        Error::Protocol(err)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::HandshakeTimeout => io::Error::new(io::ErrorKind::TimedOut, err),
+            Error::Io(err) => err,
+            Error::Protocol(err) => err.into(),
+        }
+    }
+}
+
+/// A wrapper around a [`Config`] used to negotiate TLS connections in the client mode.
+#[derive(Clone)]
+pub struct TlsConnector {
+    config: Config,
+    timeout: Duration,
+}
+
+impl TlsConnector {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+
+    /// Sets the maximum time a handshake started by this connector may take.
+    ///
+    /// When the timeout elapses the handshake future resolves to
+    /// [`Error::HandshakeTimeout`] and the underlying stream is dropped.
+    pub fn with_handshake_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Negotiates a new client TLS connection over `stream`.
+    pub async fn connect<S>(&self, domain: &str, stream: S) -> Result<TlsStream<S>, Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut conn = Connection::new_client();
+        conn.set_config(self.config.clone())?;
+        conn.set_server_name(domain)?;
+        TlsStream::open(conn, stream, self.timeout).await
+    }
+
+    /// Resolves `addr`, opens a [`TcpStream`] to it, and negotiates a new client
+    /// TLS connection, using `server_name` for SNI and certificate verification.
+    ///
+    /// This removes the resolve/connect/set-server-name boilerplate that every
+    /// caller would otherwise repeat, and avoids the common footgun of
+    /// forgetting to set the SNI name.
+    pub async fn connect_to<A: ToSocketAddrs>(
+        &self,
+        addr: A,
+        server_name: &str,
+    ) -> Result<TlsStream<TcpStream>, Error> {
+        let stream = TcpStream::connect(addr).await.map_err(Error::Io)?;
+        self.connect(server_name, stream).await
+    }
+
+    /// Like [`Self::connect_to`], but derives the SNI server name from the host
+    /// portion of a single `"host:port"` string.
+    ///
+    /// The host is resolved to open the [`TcpStream`], while the unresolved host
+    /// name is used for SNI and certificate verification.
+    pub async fn connect_to_host(&self, addr: &str) -> Result<TlsStream<TcpStream>, Error> {
+        // A bracketed IPv6 literal (`[::1]:443`) must have its brackets stripped
+        // before the host can be used for SNI; a plain `host:port` splits on the
+        // final colon.
+        let host = if let Some(rest) = addr.strip_prefix('[') {
+            rest.split_once("]:")
+                .map(|(host, _port)| host)
+                .ok_or(S2NError::INVALID_INPUT)?
+        } else {
+            addr.rsplit_once(':')
+                .map(|(host, _port)| host)
+                .ok_or(S2NError::INVALID_INPUT)?
+        };
+        self.connect_to(addr, host).await
+    }
+}
+
+/// A wrapper around a [`Config`] used to negotiate TLS connections in the server mode.
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    config: Config,
+    timeout: Duration,
+}
+
+impl TlsAcceptor {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+
+    /// Sets the maximum time a handshake accepted by this acceptor may take.
+    ///
+    /// When the timeout elapses the handshake future resolves to
+    /// [`Error::HandshakeTimeout`] and the underlying stream is dropped.
+    pub fn with_handshake_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Negotiates a new server TLS connection over `stream`.
+    pub async fn accept<S>(&self, stream: S) -> Result<TlsStream<S>, Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut conn = Connection::new_server();
+        conn.set_config(self.config.clone())?;
+        TlsStream::open(conn, stream, self.timeout).await
+    }
+}
+
+/// Context handed to the s2n-tls send and receive callbacks.
+///
+/// It carries raw pointers to the current task [`Context`] and the underlying
+/// transport so the callbacks can drive the stream while a poll is in flight.
+/// The pointers are only valid for the duration of a single call into s2n-tls
+/// and are cleared by [`TlsStream::with_io`] as soon as that call returns.
+struct CallbackContext {
+    stream: *mut c_void,
+    task: *mut c_void,
+    // Stashes the `io::Error` from a failed transport read/write so the s2n
+    // error that surfaces from the poll can carry it as its `source()`.
+    error: Option<io::Error>,
+}
+
+/// A TLS stream created by a [`TlsConnector`] or [`TlsAcceptor`].
+///
+/// Application data written to and read from the stream is encrypted using the
+/// negotiated s2n-tls [`Connection`] and shuttled over the inner transport `S`.
+pub struct TlsStream<S> {
+    conn: Connection,
+    stream: S,
+}
+
+impl<S> TlsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Drives `conn` to a completed handshake over `stream`, bounded by `timeout`.
+    ///
+    /// If the timeout elapses first, the future resolves to
+    /// [`Error::HandshakeTimeout`] and `tls` (and therefore the inner stream)
+    /// is dropped, so no descriptor is leaked.
+    async fn open(conn: Connection, stream: S, timeout: Duration) -> Result<Self, Error> {
+        let mut tls = TlsStream { conn, stream };
+        match tokio::time::timeout(timeout, Negotiate(&mut tls)).await {
+            Ok(result) => result?,
+            Err(_) => return Err(Error::HandshakeTimeout),
+        }
+        Ok(tls)
+    }
+
+    /// Returns a shared reference to the underlying s2n-tls connection.
+    pub fn as_ref(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Returns a mutable reference to the underlying s2n-tls connection.
+    pub fn as_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+
+    /// Registers the transport with the connection, invokes `action`, then
+    /// tears the registration back down so no pointer outlives the call.
+    fn with_io<F, R>(&mut self, cx: &mut Context, action: F) -> Poll<Result<R, S2NError>>
+    where
+        F: FnOnce(&mut Connection) -> Poll<Result<R, S2NError>>,
+    {
+        let Self { conn, stream } = self;
+        conn.set_waker(Some(cx.waker()))?;
+
+        let mut context = CallbackContext {
+            stream: stream as *mut S as *mut c_void,
+            task: cx as *mut Context as *mut c_void,
+            error: None,
+        };
+        let context_ptr = &mut context as *mut CallbackContext as *mut c_void;
+
+        unsafe {
+            conn.set_receive_callback(Some(recv_io_cb::<S>))?;
+            conn.set_receive_context(context_ptr)?;
+            conn.set_send_callback(Some(send_io_cb::<S>))?;
+            conn.set_send_context(context_ptr)?;
+        }
+
+        let result = action(conn);
+
+        // The context lives on the stack and must not be observable by the
+        // callbacks once this function returns.
+        unsafe {
+            conn.set_receive_context(core::ptr::null_mut())?;
+            conn.set_send_context(core::ptr::null_mut())?;
+        }
+
+        // If a transport callback failed, s2n surfaced it as an opaque IOError;
+        // reattach the original `io::Error` as the error's source so the chain
+        // reaches back down to the transport that actually failed.
+        match result {
+            Poll::Ready(Err(err)) => {
+                let err = match context.error.take() {
+                    Some(io_err) => err.with_source(io_err),
+                    None => err,
+                };
+                Poll::Ready(Err(err))
+            }
+            other => other,
+        }
+    }
+}
+
+/// Future that runs [`Connection::poll_negotiate`] to completion.
+struct Negotiate<'a, S>(&'a mut TlsStream<S>);
+
+impl<S> Future for Negotiate<'_, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.0
+            .with_io(cx, |conn| conn.poll_negotiate().map_ok(|_| ()))
+            .map_err(Error::from)
+    }
+}
+
+impl<S> AsyncRead for TlsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.with_io(cx, |conn| conn.poll_recv_uninitialized(buf.unfilled_mut())) {
+            // A `poll_recv` of 0 bytes means the peer sent a close_notify alert:
+            // report it as a clean EOF by leaving the buffer unfilled.
+            Poll::Ready(Ok(len)) => {
+                // Safety: poll_recv_uninitialized guarantees the first `len`
+                // bytes of the unfilled region have been initialized.
+                unsafe { buf.assume_init(len) };
+                buf.advance(len);
+                Poll::Ready(Ok(()))
+            }
+            // The transport hit EOF or was reset before a close_notify arrived.
+            // A reset surfaces as an `IOError`, while a plain FIN (the recv
+            // callback returning 0) surfaces as `S2N_ERR_CLOSED`
+            // (`ConnectionClosed`); both are truncations, so report them as
+            // `UnexpectedEof` to distinguish them from a graceful close.
+            Poll::Ready(Err(err))
+                if matches!(
+                    err.kind(),
+                    ErrorType::IOError | ErrorType::ConnectionClosed
+                ) =>
+            {
+                Poll::Ready(Err(io::Error::new(io::ErrorKind::UnexpectedEof, err)))
+            }
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S> AsyncWrite for TlsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.with_io(cx, |conn| conn.poll_send(buf))
+            .map_err(io::Error::from)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.with_io(cx, |conn| conn.poll_flush().map_ok(|_| ()))
+            .map_err(io::Error::from)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        // Send our own close_notify and flush it before tearing down the
+        // inner transport, so the peer observes a graceful close.
+        match this.with_io(cx, |conn| conn.poll_shutdown().map_ok(|_| ())) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+            Poll::Pending => return Poll::Pending,
+        }
+        Pin::new(&mut this.stream).poll_shutdown(cx)
+    }
+}
+
+impl<S> TlsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Closes the write half of the connection.
+    ///
+    /// Unlike [`AsyncWrite::poll_shutdown`], this sends a close_notify without
+    /// waiting for the peer to respond and without shutting down the inner
+    /// transport. When using TLS1.3 the read half stays open, so the caller can
+    /// keep draining application data the peer sent before it closes its own
+    /// write half.
+    pub fn poll_shutdown_send(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.with_io(cx, |conn| conn.poll_shutdown_send().map_ok(|_| ()))
+            .map_err(io::Error::from)
+    }
+}
+
+impl<S> fmt::Debug for TlsStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsStream")
+            .field("connection", &self.conn)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receive callback registered on the connection while a poll is in flight.
+///
+/// Returns the number of bytes read, `0` on clean EOF, or `-1` with `errno`
+/// set to `EWOULDBLOCK` when the transport has no data ready yet.
+unsafe extern "C" fn recv_io_cb<S: AsyncRead + Unpin>(
+    context: *mut c_void,
+    data: *mut u8,
+    len: u32,
+) -> c_int {
+    let context = &mut *(context as *mut CallbackContext);
+    let stream = Pin::new(&mut *(context.stream as *mut S));
+    let task = &mut *(context.task as *mut Context);
+
+    let slice =
+        core::slice::from_raw_parts_mut(data as *mut core::mem::MaybeUninit<u8>, len as usize);
+    let mut buf = ReadBuf::uninit(slice);
+    match stream.poll_read(task, &mut buf) {
+        Poll::Ready(Ok(())) => buf.filled().len() as c_int,
+        Poll::Ready(Err(err)) => {
+            set_errno(Errno(err.raw_os_error().unwrap_or(libc::EIO)));
+            context.error = Some(err);
+            -1
+        }
+        Poll::Pending => {
+            set_errno(Errno(libc::EWOULDBLOCK));
+            -1
+        }
+    }
+}
+
+/// Send callback registered on the connection while a poll is in flight.
+unsafe extern "C" fn send_io_cb<S: AsyncWrite + Unpin>(
+    context: *mut c_void,
+    data: *const u8,
+    len: u32,
+) -> c_int {
+    let context = &mut *(context as *mut CallbackContext);
+    let stream = Pin::new(&mut *(context.stream as *mut S));
+    let task = &mut *(context.task as *mut Context);
+
+    let slice = core::slice::from_raw_parts(data, len as usize);
+    match stream.poll_write(task, slice) {
+        Poll::Ready(Ok(written)) => written as c_int,
+        Poll::Ready(Err(err)) => {
+            set_errno(Errno(err.raw_os_error().unwrap_or(libc::EIO)));
+            context.error = Some(err);
+            -1
+        }
+        Poll::Pending => {
+            set_errno(Errno(libc::EWOULDBLOCK));
+            -1
+        }
+    }
+}
+
+/// A partially-accepted connection that has parsed the peer's ClientHello but
+/// has not yet chosen a [`Config`] or completed the handshake.
+///
+/// This mirrors the lazy acceptor in `tokio-rustls`: the ClientHello is read
+/// off the wire so the SNI server name and offered ALPN protocols can be
+/// inspected, and the appropriate [`Config`] selected before negotiation is
+/// resumed with [`StartHandshake::into_stream`] on the same socket.
+///
+/// The `config` passed to [`Self::new`] only has to drive the connection far
+/// enough to parse the ClientHello; it must register a nonblocking ClientHello
+/// callback so the handshake pauses there instead of running to completion with
+/// the wrong config. The real config is supplied later to
+/// [`StartHandshake::into_stream`].
+///
+/// ```no_run
+/// # async fn example<S>(base: s2n_tls::config::Config, stream: S) -> Result<(), s2n_tls_tokio::Error>
+/// # where S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin {
+/// use s2n_tls_tokio::LazyConfigAcceptor;
+/// # fn config_for(_: Option<&str>) -> s2n_tls::config::Config { unimplemented!() }
+///
+/// let accept = LazyConfigAcceptor::new(base, stream);
+/// let handshake = accept.await?;
+/// let config = config_for(handshake.server_name());
+/// let _stream = handshake.into_stream(config).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LazyConfigAcceptor<S> {
+    stream: Option<TlsStream<S>>,
+    // Deferred so a `set_config` failure can be reported from `poll` rather than
+    // panicking in the infallible `new`.
+    setup_error: Option<S2NError>,
+}
+
+impl<S> LazyConfigAcceptor<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub fn new(config: Config, stream: S) -> Self {
+        let mut conn = Connection::new(Mode::Server);
+        let setup_error = conn.set_config(config).err();
+        Self {
+            stream: Some(TlsStream { conn, stream }),
+            setup_error,
+        }
+    }
+}
+
+impl<S> Future for LazyConfigAcceptor<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Output = Result<StartHandshake<S>, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Some(err) = self.setup_error.take() {
+            return Poll::Ready(Err(err.into()));
+        }
+
+        let tls = self
+            .stream
+            .as_mut()
+            .expect("LazyConfigAcceptor polled after completion");
+
+        // Drive the handshake. The config's nonblocking ClientHello callback
+        // pauses negotiation once the ClientHello is parsed, which surfaces as
+        // `Poll::Pending`; at that point `client_hello()` becomes readable and
+        // we hand control back so the caller can choose the real config. The
+        // buffered bytes stay on the connection, so resuming continues the same
+        // negotiation rather than restarting it.
+        match tls.with_io(cx, |conn| match conn.poll_negotiate() {
+            Poll::Ready(res) => Poll::Ready(res.map(|_| ())),
+            Poll::Pending if conn.client_hello().is_ok() => Poll::Ready(Ok(())),
+            Poll::Pending => Poll::Pending,
+        }) {
+            Poll::Ready(Ok(())) => {
+                let tls = self.stream.take().unwrap();
+                Poll::Ready(Ok(StartHandshake { inner: tls }))
+            }
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A handle returned by [`LazyConfigAcceptor`] once the ClientHello has been read.
+pub struct StartHandshake<S> {
+    inner: TlsStream<S>,
+}
+
+impl<S> StartHandshake<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Returns the SNI server name offered in the ClientHello, if any.
+    pub fn server_name(&self) -> Option<&str> {
+        self.inner.conn.server_name()
+    }
+
+    /// Returns a reference to the parsed ClientHello for further inspection,
+    /// for example of the offered ALPN protocols.
+    pub fn client_hello(&self) -> Result<&s2n_tls::client_hello::ClientHello, S2NError> {
+        self.inner.conn.client_hello()
+    }
+
+    /// Resumes the handshake using `config` and returns the negotiated stream.
+    ///
+    /// The buffered ClientHello is reused, so negotiation continues on the same
+    /// socket. If `config` selects a certificate that doesn't match the offered
+    /// server name, the resume will fail like any other handshake error.
+    pub async fn into_stream(mut self, config: Config) -> Result<TlsStream<S>, Error> {
+        self.inner.conn.set_config(config)?;
+        // Release the nonblocking ClientHello callback so negotiation resumes
+        // with the newly selected config.
+        self.inner.conn.mark_client_hello_cb_done()?;
+        Negotiate(&mut self.inner).await?;
+        Ok(self.inner)
+    }
+}
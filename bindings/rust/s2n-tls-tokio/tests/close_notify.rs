@@ -0,0 +1,88 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use s2n_tls_tokio::{TlsAcceptor, TlsConnector};
+use std::future::poll_fn;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+mod common;
+
+const TEST_DATA: &[u8] = "hello world".as_bytes();
+
+// A close_notify from the peer should surface as a clean EOF, so that
+// `read_to_end` returns all buffered application data and then terminates.
+#[tokio::test]
+async fn close_notify_returns_clean_eof() -> Result<(), Box<dyn std::error::Error>> {
+    let (server_stream, client_stream) = common::get_streams().await?;
+
+    let connector = TlsConnector::new(common::client_config()?.build()?);
+    let acceptor = TlsAcceptor::new(common::server_config()?.build()?);
+
+    let (mut client, mut server) =
+        common::run_negotiate(connector, client_stream, acceptor, server_stream).await?;
+
+    // The server's graceful shutdown completes only once the client answers
+    // with its own close_notify, so both sides run concurrently.
+    let mut received = Vec::new();
+    tokio::try_join!(
+        async {
+            server.write_all(TEST_DATA).await?;
+            server.shutdown().await
+        },
+        async {
+            // All application data is delivered, and then the stream reports EOF.
+            client.read_to_end(&mut received).await?;
+            client.shutdown().await
+        },
+    )?;
+    assert_eq!(TEST_DATA, received);
+
+    Ok(())
+}
+
+// Closing only the write half leaves the read half open so the peer's
+// application data can still be drained.
+#[tokio::test]
+async fn half_close_keeps_read_half_open() -> Result<(), Box<dyn std::error::Error>> {
+    let (server_stream, client_stream) = common::get_streams().await?;
+
+    let connector = TlsConnector::new(common::client_config()?.build()?);
+    let acceptor = TlsAcceptor::new(common::server_config()?.build()?);
+
+    let (mut client, mut server) =
+        common::run_negotiate(connector, client_stream, acceptor, server_stream).await?;
+
+    // Client closes its write half only.
+    poll_fn(|cx| client.poll_shutdown_send(cx)).await?;
+
+    // The server can still send application data, which the client reads.
+    server.write_all(TEST_DATA).await?;
+    let mut received = [0; TEST_DATA.len()];
+    assert_eq!(client.read_exact(&mut received).await?, TEST_DATA.len());
+    assert_eq!(TEST_DATA, received);
+
+    Ok(())
+}
+
+// An abrupt close of the transport without a close_notify is a possible
+// truncation attack and must surface as an error, not a clean EOF.
+#[tokio::test]
+async fn reset_without_close_notify_errors() -> Result<(), Box<dyn std::error::Error>> {
+    let (server_stream, client_stream) = common::get_streams().await?;
+
+    let connector = TlsConnector::new(common::client_config()?.build()?);
+    let acceptor = TlsAcceptor::new(common::server_config()?.build()?);
+
+    let (mut client, server) =
+        common::run_negotiate(connector, client_stream, acceptor, server_stream).await?;
+
+    // Drop the server without a graceful shutdown, closing the transport
+    // without ever sending a close_notify.
+    drop(server);
+
+    let mut received = [0; TEST_DATA.len()];
+    let err = client.read(&mut received).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    Ok(())
+}
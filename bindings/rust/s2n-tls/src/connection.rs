@@ -15,7 +15,9 @@ use crate::{
 use core::{
     convert::TryInto,
     fmt,
+    marker::PhantomData,
     mem::{self, ManuallyDrop, MaybeUninit},
+    ops::{Deref, DerefMut},
     pin::Pin,
     ptr::NonNull,
     task::{Poll, Waker},
@@ -23,11 +25,19 @@ use core::{
 };
 use libc::c_void;
 use s2n_tls_sys::*;
-use std::{any::Any, ffi::CStr};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    ffi::CStr,
+    os::unix::io::RawFd,
+};
 
 mod builder;
 pub use builder::*;
 
+mod pool;
+pub use pool::*;
+
 macro_rules! static_const_str {
     ($c_chars:expr) => {
         unsafe { CStr::from_ptr($c_chars) }
@@ -45,6 +55,170 @@ pub struct KeyUpdateCount {
     pub recv_key_updates: u8,
 }
 
+/// The outcome of ECH (Encrypted Client Hello) negotiation.
+///
+/// When the server rejects ECH it falls back to the public name, which the
+/// client must validate the certificate against rather than the private SNI.
+#[cfg(feature = "unstable-ech")]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EchNegotiationStatus {
+    Accepted,
+    Rejected { public_name: String },
+    NotRequested,
+}
+
+/// A single TLS session ticket received from the peer.
+///
+/// The ticket is owned by the connection that produced it and is only valid for
+/// the duration of the [`SessionTicketCallback`] invocation that surfaced it.
+#[repr(transparent)]
+pub struct SessionTicket(s2n_session_ticket);
+
+impl SessionTicket {
+    pub(crate) fn from_ptr(ticket: &s2n_session_ticket) -> &Self {
+        // SAFETY: SessionTicket is a transparent wrapper around s2n_session_ticket
+        unsafe { &*(ticket as *const s2n_session_ticket as *const SessionTicket) }
+    }
+
+    /// Returns the length in bytes of the serialized ticket.
+    pub fn len(&self) -> Result<usize, Error> {
+        let mut len = 0;
+        unsafe {
+            s2n_session_ticket_get_data_len(&self.0 as *const _ as *mut _, &mut len).into_result()?;
+        }
+        Ok(len)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Serializes the ticket into `output`, returning the number of bytes written.
+    ///
+    /// Returns `Error::INVALID_INPUT` if `output` is too small to hold the ticket.
+    pub fn data(&self, output: &mut [u8]) -> Result<usize, Error> {
+        let len = self.len()?;
+        if output.len() < len {
+            return Err(Error::INVALID_INPUT);
+        }
+        unsafe {
+            s2n_session_ticket_get_data(
+                &self.0 as *const _ as *mut _,
+                output.len(),
+                output.as_mut_ptr(),
+            )
+            .into_result()?;
+        }
+        Ok(len)
+    }
+
+    /// Returns the lifetime hint advertised by the server for this ticket.
+    pub fn lifetime(&self) -> Result<Duration, Error> {
+        let mut lifetime = 0;
+        unsafe {
+            s2n_session_ticket_get_lifetime(&self.0 as *const _ as *mut _, &mut lifetime)
+                .into_result()?;
+        }
+        Ok(Duration::from_secs(lifetime as u64))
+    }
+}
+
+/// A handshake message an extension can be attached to.
+#[cfg(feature = "unstable-custom-extensions")]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HandshakeMessage {
+    ClientHello,
+    ServerHello,
+    EncryptedExtensions,
+    Certificate,
+}
+
+#[cfg(feature = "unstable-custom-extensions")]
+impl TryFrom<s2n_tls_hello_message_type::Type> for HandshakeMessage {
+    type Error = Error;
+
+    fn try_from(input: s2n_tls_hello_message_type::Type) -> Result<Self, Self::Error> {
+        match input {
+            s2n_tls_hello_message_type::CLIENT_HELLO => Ok(HandshakeMessage::ClientHello),
+            s2n_tls_hello_message_type::SERVER_HELLO => Ok(HandshakeMessage::ServerHello),
+            s2n_tls_hello_message_type::ENCRYPTED_EXTENSIONS => {
+                Ok(HandshakeMessage::EncryptedExtensions)
+            }
+            s2n_tls_hello_message_type::CERTIFICATE => Ok(HandshakeMessage::Certificate),
+            _ => Err(Error::INVALID_INPUT),
+        }
+    }
+}
+
+/// The result of processing a peer's extension, mapped to an alert on reject.
+#[cfg(feature = "unstable-custom-extensions")]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExtensionResult {
+    Accepted,
+    Rejected,
+}
+
+/// A handler for an application-defined TLS extension code point.
+///
+/// Modeled on neqo's extension handler, this lets an application implement
+/// extensions s2n-tls doesn't natively model without forking the crate. The
+/// handler is consulted both when building a handshake message and when the
+/// peer's extension of the same type is received.
+#[cfg(feature = "unstable-custom-extensions")]
+pub trait ExtensionHandler: 'static + Send + Sync {
+    /// The extension code point this handler is responsible for.
+    fn extension_type(&self) -> u16;
+
+    /// Invoked while building `message`; returns the extension bytes to write,
+    /// or `None` to omit the extension from that message.
+    fn write(&mut self, message: HandshakeMessage) -> Option<Vec<u8>>;
+
+    /// Invoked when the peer's extension of this type is received on `message`.
+    fn receive(&mut self, message: HandshakeMessage, extension_data: &[u8]) -> ExtensionResult;
+}
+
+/// An application-controlled store for stateful (session-ID based) resumption.
+///
+/// Analogous to rustls's `StoresServerSessions`, this backs TLS1.2 session-ID
+/// resumption and TLS1.3 ticket storage with a caller-provided backend such as
+/// an in-memory LRU or Redis. Both keys and values are highly sensitive opaque
+/// blobs and must be treated as secrets. A single store registered on a shared
+/// config is consulted by every connection using it.
+pub trait SessionCache: 'static + Send + Sync {
+    /// Stores `value` under `key`, returning whether the entry was accepted.
+    fn put(&self, key: &[u8], value: &[u8]) -> bool;
+
+    /// Retrieves the value previously stored under `key`.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// Removes the entry stored under `key`, returning whether one was present.
+    fn remove(&self, key: &[u8]) -> bool;
+}
+
+/// A callback that receives key-log lines as secrets are derived.
+///
+/// Mirroring rustls's `KeyLog`, this enables writing `SSLKEYLOGFILE`-format
+/// output (`CLIENT_HANDSHAKE_TRAFFIC_SECRET`, `SERVER_TRAFFIC_SECRET_0`,
+/// `CLIENT_RANDOM`, etc.) so a packet capture can be decrypted in dev/test
+/// without disabling encryption. s2n-tls delivers each already-formatted line,
+/// so the handler can append it to a file verbatim.
+pub trait KeyLogCallback: 'static + Send + Sync {
+    fn log(&mut self, key_log_line: &[u8]);
+}
+
+/// A callback invoked once for each session ticket received after the handshake.
+///
+/// Unlike [`Connection::session_ticket`], which only exposes the most recently
+/// received TLS1.3 ticket, this fires for every `NewSessionTicket` the server
+/// sends, so clients that open many resumed connections can persist them all.
+/// Tickets are highly sensitive and must be stored securely.
+pub trait SessionTicketCallback: 'static + Send + Sync {
+    fn on_session_ticket(&mut self, ticket: &SessionTicket);
+}
+
 pub struct Connection {
     connection: NonNull<s2n_connection>,
 }
@@ -325,6 +499,79 @@ impl Connection {
         })
     }
 
+    /// Offloads sending of application records to the kernel TLS implementation.
+    ///
+    /// This must be called after [`Self::poll_negotiate`] has succeeded. It
+    /// returns an error when the negotiated cipher suite or the running kernel
+    /// doesn't support TLS offload. Whether offload actually engaged can be
+    /// checked with [`Self::ktls_send_enabled`].
+    #[cfg(feature = "unstable-ktls")]
+    pub fn ktls_enable_send(&mut self) -> Result<&mut Self, Error> {
+        unsafe { s2n_connection_ktls_enable_send(self.connection.as_ptr()).into_result() }?;
+        self.context_mut().ktls_send_enabled = true;
+        Ok(self)
+    }
+
+    /// Offloads receiving of application records to the kernel TLS implementation.
+    ///
+    /// This must be called after [`Self::poll_negotiate`] has succeeded. It
+    /// returns an error when the negotiated cipher suite or the running kernel
+    /// doesn't support TLS offload. Whether offload actually engaged can be
+    /// checked with [`Self::ktls_recv_enabled`].
+    #[cfg(feature = "unstable-ktls")]
+    pub fn ktls_enable_recv(&mut self) -> Result<&mut Self, Error> {
+        unsafe { s2n_connection_ktls_enable_recv(self.connection.as_ptr()).into_result() }?;
+        self.context_mut().ktls_recv_enabled = true;
+        Ok(self)
+    }
+
+    /// Reports whether send offload was successfully engaged via
+    /// [`Self::ktls_enable_send`].
+    #[cfg(feature = "unstable-ktls")]
+    pub fn ktls_send_enabled(&self) -> bool {
+        self.context().ktls_send_enabled
+    }
+
+    /// Reports whether receive offload was successfully engaged via
+    /// [`Self::ktls_enable_recv`].
+    #[cfg(feature = "unstable-ktls")]
+    pub fn ktls_recv_enabled(&self) -> bool {
+        self.context().ktls_recv_enabled
+    }
+
+    /// Sends `count` bytes starting at `offset` of the file referenced by `fd`
+    /// directly through the kernel TLS socket.
+    ///
+    /// This requires send offload to have been enabled with
+    /// [`Self::ktls_enable_send`]. Because the kernel reads the plaintext from
+    /// the file and encrypts it in place, the contents never need to be copied
+    /// into a userspace buffer, which is ideal for static-file servers.
+    ///
+    /// Returns the number of bytes written, and may indicate a partial write.
+    #[cfg(feature = "unstable-ktls")]
+    pub fn poll_sendfile(
+        &mut self,
+        fd: RawFd,
+        offset: u64,
+        count: usize,
+    ) -> Poll<Result<usize, Error>> {
+        let mut bytes_written = 0;
+        let mut blocked = s2n_blocked_status::NOT_BLOCKED;
+        let offset: libc::off_t = offset.try_into().map_err(|_| Error::INVALID_INPUT)?;
+        unsafe {
+            s2n_connection_ktls_sendfile(
+                self.connection.as_ptr(),
+                fd,
+                offset,
+                count,
+                &mut bytes_written,
+                &mut blocked,
+            )
+            .into_poll()
+            .map_ok(|_| bytes_written)
+        }
+    }
+
     /// sets the application protocol preferences on an s2n_connection object.
     ///
     /// protocols is a list in order of preference, with most preferred protocol first, and of
@@ -429,6 +676,37 @@ impl Connection {
         Ok(self)
     }
 
+    /// Sets the file descriptor used for both reading and writing.
+    ///
+    /// This is an alternative to [`Self::set_receive_callback`] and
+    /// [`Self::set_send_callback`] that hands s2n-tls a socket directly. It is
+    /// also the basis for a STARTTLS-style upgrade: an application that has
+    /// already spoken a plaintext protocol (SMTP, IMAP, XMPP) on a socket can
+    /// attach that same descriptor to a freshly created [`Connection`] and call
+    /// [`Self::poll_negotiate`] to begin TLS in place.
+    pub fn set_fd(&mut self, fd: RawFd) -> Result<&mut Self, Error> {
+        unsafe { s2n_connection_set_fd(self.connection.as_ptr(), fd).into_result() }?;
+        Ok(self)
+    }
+
+    /// Sets the file descriptor used for reading.
+    ///
+    /// Use this together with [`Self::set_write_fd`] when reads and writes go to
+    /// different descriptors.
+    pub fn set_read_fd(&mut self, fd: RawFd) -> Result<&mut Self, Error> {
+        unsafe { s2n_connection_set_read_fd(self.connection.as_ptr(), fd).into_result() }?;
+        Ok(self)
+    }
+
+    /// Sets the file descriptor used for writing.
+    ///
+    /// Use this together with [`Self::set_read_fd`] when reads and writes go to
+    /// different descriptors.
+    pub fn set_write_fd(&mut self, fd: RawFd) -> Result<&mut Self, Error> {
+        unsafe { s2n_connection_set_write_fd(self.connection.as_ptr(), fd).into_result() }?;
+        Ok(self)
+    }
+
     /// Connections prefering low latency will be encrypted using small record sizes that
     /// can be decrypted sooner by the recipient.
     pub fn prefer_low_latency(&mut self) -> Result<&mut Self, Error> {
@@ -518,6 +796,11 @@ impl Connection {
 
             match res {
                 Poll::Ready(res) => {
+                    // On an ECH rejection, attach the server's retry_configs to
+                    // the error so the client can pull them out and reconnect.
+                    #[cfg(feature = "unstable-ech")]
+                    let res =
+                        res.map_err(|err| err.with_ech_retry_config(self.connection.as_ptr()));
                     let res = res.map(|_| self);
                     return Poll::Ready(res);
                 }
@@ -670,6 +953,58 @@ impl Connection {
         }
     }
 
+    /// Sets the ECHConfigList used to encrypt the inner ClientHello.
+    ///
+    /// The list is the HPKE-encrypted-ClientHello configuration published in a
+    /// DNS `HTTPS`/`SVCB` record. This is a client-side API; after the handshake
+    /// the outcome can be inspected with [`Self::ech_negotiation_status`].
+    #[cfg(feature = "unstable-ech")]
+    pub fn set_ech_config_list(&mut self, config_list: &[u8]) -> Result<&mut Self, Error> {
+        unsafe {
+            s2n_connection_set_ech_config_list(
+                self.connection.as_ptr(),
+                config_list.as_ptr(),
+                config_list
+                    .len()
+                    .try_into()
+                    .map_err(|_| Error::INVALID_INPUT)?,
+            )
+            .into_result()
+        }?;
+        Ok(self)
+    }
+
+    /// Reports whether ECH was accepted, rejected, or never requested.
+    ///
+    /// When a server cannot decrypt the inner ClientHello it falls back to the
+    /// ECH public name. In that case the returned [`EchNegotiationStatus::Rejected`]
+    /// carries that public name, which the application must validate the
+    /// certificate against instead of the private SNI before deciding whether
+    /// to continue or abort.
+    #[cfg(feature = "unstable-ech")]
+    pub fn ech_negotiation_status(&self) -> Result<EchNegotiationStatus, Error> {
+        let mut status = s2n_ech_negotiation_status::NOT_REQUESTED;
+        unsafe {
+            s2n_connection_get_ech_negotiation_status(self.connection.as_ptr(), &mut status)
+                .into_result()?;
+        }
+        match status {
+            s2n_ech_negotiation_status::ACCEPTED => Ok(EchNegotiationStatus::Accepted),
+            s2n_ech_negotiation_status::REJECTED => {
+                let public_name = unsafe {
+                    let name = s2n_connection_get_ech_public_name(self.connection.as_ptr())
+                        .into_result()?;
+                    CStr::from_ptr(name)
+                        .to_str()
+                        .map_err(|_| Error::INVALID_INPUT)?
+                        .to_owned()
+                };
+                Ok(EchNegotiationStatus::Rejected { public_name })
+            }
+            _ => Ok(EchNegotiationStatus::NotRequested),
+        }
+    }
+
     /// Adds a session ticket from a previous TLS connection to create a resumed session
     pub fn set_session_ticket(&mut self, session: &[u8]) -> Result<&mut Self, Error> {
         unsafe {
@@ -706,6 +1041,272 @@ impl Connection {
         Ok(written.try_into().unwrap())
     }
 
+    /// Registers a callback that fires for each post-handshake session ticket.
+    ///
+    /// The handler is owned by this connection's [`Context`] and dispatched from
+    /// an FFI trampoline, receiving the serialized ticket blob and its lifetime
+    /// hint. This collects every TLS1.3 ticket the server sends, rather than
+    /// just the most recent one returned by [`Self::session_ticket`].
+    ///
+    /// The underlying s2n-tls callback is configured on the connection's
+    /// [`Config`], which is refcounted and may be shared. The callback context
+    /// is this connection's `Context`, so tickets are routed back to this
+    /// handler; registering it on connections that share a `Config` is
+    /// last-writer-wins, so set it on a connection whose `Config` is not shared.
+    pub fn set_session_ticket_callback<T: 'static + SessionTicketCallback>(
+        &mut self,
+        handler: T,
+    ) -> Result<&mut Self, Error> {
+        unsafe extern "C" fn session_ticket_cb(
+            _conn: *mut s2n_connection,
+            context: *mut c_void,
+            ticket: *mut s2n_session_ticket,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            if let Some(handler) = context.session_ticket_callback.as_mut() {
+                handler.on_session_ticket(SessionTicket::from_ptr(&*ticket));
+            }
+            0
+        }
+
+        self.context_mut().session_ticket_callback = Some(Box::new(handler));
+
+        let context = self.context_mut() as *mut Context as *mut c_void;
+        let mut config = core::ptr::null_mut();
+        unsafe {
+            s2n_connection_get_config(self.connection.as_ptr(), &mut config).into_result()?;
+            s2n_config_set_session_ticket_cb(config, Some(session_ticket_cb), context)
+                .into_result()?;
+        }
+        Ok(self)
+    }
+
+    /// Registers a handler for an application-defined TLS extension.
+    ///
+    /// The handler is consulted while building handshake messages and when the
+    /// peer sends an extension with the handler's code point. Handlers are kept
+    /// alive on the connection [`Context`] for the connection lifetime and
+    /// dispatched from FFI trampolines that locate the handler by extension type.
+    #[cfg(feature = "unstable-custom-extensions")]
+    pub fn add_extension_handler<T: 'static + ExtensionHandler>(
+        &mut self,
+        handler: T,
+    ) -> Result<&mut Self, Error> {
+        unsafe extern "C" fn write_cb(
+            context: *mut c_void,
+            message: s2n_tls_hello_message_type::Type,
+            extension_type: u16,
+            output: *mut u8,
+            output_len: *mut u32,
+            max_len: u32,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let message = match HandshakeMessage::try_from(message) {
+                Ok(message) => message,
+                Err(_) => return -1,
+            };
+            let handler = match context
+                .extension_handlers
+                .iter_mut()
+                .find(|handler| handler.extension_type() == extension_type)
+            {
+                Some(handler) => handler,
+                None => return -1,
+            };
+            match handler.write(message) {
+                Some(bytes) if bytes.len() <= max_len as usize => {
+                    core::ptr::copy_nonoverlapping(bytes.as_ptr(), output, bytes.len());
+                    *output_len = bytes.len() as u32;
+                    0
+                }
+                // Omit the extension, or signal that it didn't fit.
+                Some(_) => -1,
+                None => {
+                    *output_len = 0;
+                    0
+                }
+            }
+        }
+
+        unsafe extern "C" fn recv_cb(
+            context: *mut c_void,
+            message: s2n_tls_hello_message_type::Type,
+            extension_type: u16,
+            extension_data: *const u8,
+            extension_len: u32,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let message = match HandshakeMessage::try_from(message) {
+                Ok(message) => message,
+                Err(_) => return -1,
+            };
+            let handler = match context
+                .extension_handlers
+                .iter_mut()
+                .find(|handler| handler.extension_type() == extension_type)
+            {
+                Some(handler) => handler,
+                None => return -1,
+            };
+            let data = core::slice::from_raw_parts(extension_data, extension_len as usize);
+            match handler.receive(message, data) {
+                ExtensionResult::Accepted => 0,
+                ExtensionResult::Rejected => -1,
+            }
+        }
+
+        let extension_type = handler.extension_type();
+        self.context_mut().extension_handlers.push(Box::new(handler));
+        unsafe {
+            s2n_connection_set_extension_callbacks(
+                self.connection.as_ptr(),
+                extension_type,
+                Some(write_cb),
+                Some(recv_cb),
+                self.context_mut() as *mut Context as *mut c_void,
+            )
+            .into_result()
+        }?;
+        Ok(self)
+    }
+
+    /// Registers a callback that receives each derived secret as a key-log line.
+    ///
+    /// The handler is owned by this connection's [`Context`] and dispatched from
+    /// an FFI trampoline as the handshake progresses, enabling packet-capture
+    /// decryption via an `SSLKEYLOGFILE`. Secrets are highly sensitive: only
+    /// enable this in development or test environments.
+    ///
+    /// As with [`Self::set_session_ticket_callback`], the underlying s2n-tls
+    /// callback lives on the connection's refcounted [`Config`] with this
+    /// connection's `Context` as its context, so it is last-writer-wins across
+    /// connections that share a `Config`.
+    pub fn set_key_log_callback<T: 'static + KeyLogCallback>(
+        &mut self,
+        handler: T,
+    ) -> Result<&mut Self, Error> {
+        unsafe extern "C" fn key_log_cb(
+            context: *mut c_void,
+            _conn: *mut s2n_connection,
+            logline: *mut u8,
+            len: usize,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            if let Some(handler) = context.key_log_callback.as_mut() {
+                let line = core::slice::from_raw_parts(logline, len);
+                handler.log(line);
+            }
+            0
+        }
+
+        self.context_mut().key_log_callback = Some(Box::new(handler));
+
+        let context = self.context_mut() as *mut Context as *mut c_void;
+        let mut config = core::ptr::null_mut();
+        unsafe {
+            s2n_connection_get_config(self.connection.as_ptr(), &mut config).into_result()?;
+            s2n_config_set_key_log_cb(config, Some(key_log_cb), context).into_result()?;
+        }
+        Ok(self)
+    }
+
+    /// Registers an application-controlled [`SessionCache`] for stateful resumption.
+    ///
+    /// The store is owned by this connection's [`Context`] and driven by the
+    /// s2n-tls cache store/retrieve/delete callbacks, which are dispatched from
+    /// FFI trampolines. The callbacks are configured on the connection's
+    /// refcounted [`Config`] with this connection's `Context` as their context,
+    /// so — as with the other callback setters — registering a store is
+    /// last-writer-wins across connections that share a `Config`.
+    pub fn set_session_cache<T: 'static + SessionCache>(
+        &mut self,
+        store: T,
+    ) -> Result<&mut Self, Error> {
+        unsafe extern "C" fn cache_store_cb(
+            _conn: *mut s2n_connection,
+            context: *mut c_void,
+            _ttl: u64,
+            key: *const c_void,
+            key_size: u64,
+            value: *const c_void,
+            value_size: u64,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let store = match context.session_cache.as_ref() {
+                Some(store) => store,
+                None => return -1,
+            };
+            let key = core::slice::from_raw_parts(key as *const u8, key_size as usize);
+            let value = core::slice::from_raw_parts(value as *const u8, value_size as usize);
+            if store.put(key, value) {
+                0
+            } else {
+                -1
+            }
+        }
+
+        unsafe extern "C" fn cache_retrieve_cb(
+            _conn: *mut s2n_connection,
+            context: *mut c_void,
+            key: *const c_void,
+            key_size: u64,
+            value: *mut c_void,
+            value_size: *mut u64,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let store = match context.session_cache.as_ref() {
+                Some(store) => store,
+                None => return -1,
+            };
+            let key = core::slice::from_raw_parts(key as *const u8, key_size as usize);
+            match store.get(key) {
+                // The caller sizes the buffer from a prior probe; refuse if the
+                // value grew since then rather than overflowing it.
+                Some(data) if data.len() as u64 <= *value_size => {
+                    core::ptr::copy_nonoverlapping(data.as_ptr(), value as *mut u8, data.len());
+                    *value_size = data.len() as u64;
+                    0
+                }
+                _ => -1,
+            }
+        }
+
+        unsafe extern "C" fn cache_delete_cb(
+            _conn: *mut s2n_connection,
+            context: *mut c_void,
+            key: *const c_void,
+            key_size: u64,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let store = match context.session_cache.as_ref() {
+                Some(store) => store,
+                None => return -1,
+            };
+            let key = core::slice::from_raw_parts(key as *const u8, key_size as usize);
+            if store.remove(key) {
+                0
+            } else {
+                -1
+            }
+        }
+
+        self.context_mut().session_cache = Some(Box::new(store));
+
+        let context = self.context_mut() as *mut Context as *mut c_void;
+        let mut config = core::ptr::null_mut();
+        unsafe {
+            s2n_connection_get_config(self.connection.as_ptr(), &mut config).into_result()?;
+            s2n_config_set_cache_store_callback(config, Some(cache_store_cb), context)
+                .into_result()?;
+            s2n_config_set_cache_retrieve_callback(config, Some(cache_retrieve_cb), context)
+                .into_result()?;
+            s2n_config_set_cache_delete_callback(config, Some(cache_delete_cb), context)
+                .into_result()?;
+            s2n_config_set_session_cache_onoff(config, true).into_result()?;
+        }
+        Ok(self)
+    }
+
     /// Sets a Waker on the connection context or clears it if `None` is passed.
     pub fn set_waker(&mut self, waker: Option<&Waker>) -> Result<&mut Self, Error> {
         let ctx = self.context_mut();
@@ -843,7 +1444,9 @@ impl Connection {
         }))
     }
 
-    pub(crate) fn mark_client_hello_cb_done(&mut self) -> Result<(), Error> {
+    /// Signals that a nonblocking ClientHello callback has finished, resuming a
+    /// handshake that was paused after the ClientHello was parsed.
+    pub fn mark_client_hello_cb_done(&mut self) -> Result<(), Error> {
         unsafe {
             s2n_client_hello_cb_done(self.connection.as_ptr()).into_result()?;
         }
@@ -931,6 +1534,8 @@ impl Connection {
     /// See https://datatracker.ietf.org/doc/html/rfc5705 and https://www.rfc-editor.org/rfc/rfc8446.
     ///
     /// This is currently only available with TLS 1.3 connections which have finished a handshake.
+    /// The number of bytes written is chosen by the length of `output`. The call fails cleanly
+    /// if it is invoked before [`Self::poll_negotiate`] has returned `Poll::Ready(Ok(_))`.
     pub fn tls_exporter(
         &self,
         label: &[u8],
@@ -1055,24 +1660,63 @@ impl Connection {
     ///
     /// This API will override an existing application context set on the Connection.
     pub fn set_application_context<T: Send + Sync + 'static>(&mut self, app_context: T) {
-        self.context_mut().app_context = Some(Box::new(app_context));
+        self.context_mut()
+            .app_context
+            .insert((TypeId::of::<T>(), None), Box::new(app_context));
     }
 
     /// Retrieves a reference to the application context associated with the Connection.
     ///
-    /// If an application context hasn't already been set on the Connection, or if the set
-    /// application context isn't of type T, None will be returned.
+    /// The value is resolved through an ordered set of layers, highest-priority first: the value
+    /// set explicitly via [`Self::set_application_context()`], then the environment layer
+    /// registered with [`Self::application_context_from_env()`], then the default supplied to
+    /// [`Self::set_application_context_default()`]. If no layer holds a value of type T, None is
+    /// returned.
     ///
     /// To set a context on the connection, use [`Self::set_application_context()`]. To retrieve a
     /// mutable reference to the context, use [`Self::application_context_mut()`].
     pub fn application_context<T: Send + Sync + 'static>(&self) -> Option<&T> {
-        match self.context().app_context.as_ref() {
-            None => None,
-            // The Any trait keeps track of the application context's type. downcast_ref() returns
-            // Some only if the correct type is provided:
-            // https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_ref
-            Some(app_context) => app_context.downcast_ref::<T>(),
+        let context = self.context();
+        let type_id = TypeId::of::<T>();
+        // The Any trait keeps track of the application context's type. downcast_ref() returns
+        // Some only if the correct type is provided:
+        // https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_ref
+        context
+            .app_context
+            .get(&(type_id, None))
+            .or_else(|| context.app_context_env.get(&type_id))
+            .or_else(|| context.app_context_default.get(&type_id))
+            .and_then(|app_context| app_context.downcast_ref::<T>())
+    }
+
+    /// Registers a default application context of type T, used by
+    /// [`Self::application_context()`] only when no explicitly-set or environment value exists.
+    ///
+    /// This is the lowest-priority layer, giving deployment-time tunables a fallback value.
+    pub fn set_application_context_default<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.context_mut()
+            .app_context_default
+            .insert(TypeId::of::<T>(), Box::new(value));
+    }
+
+    /// Registers an environment-variable layer for type T by reading `var` and parsing it with
+    /// T's [`FromStr`](std::str::FromStr) implementation.
+    ///
+    /// The parsed value sits between the default and the explicitly-set value in
+    /// [`Self::application_context()`]'s precedence order. If the variable is unset (or not valid
+    /// unicode) the layer is left empty; if it is present but fails to parse, an error is
+    /// returned.
+    pub fn application_context_from_env<T>(&mut self, var: &str) -> Result<&mut Self, Error>
+    where
+        T: Send + Sync + 'static + std::str::FromStr,
+    {
+        if let Ok(raw) = std::env::var(var) {
+            let parsed = raw.parse::<T>().map_err(|_| Error::INVALID_INPUT)?;
+            self.context_mut()
+                .app_context_env
+                .insert(TypeId::of::<T>(), Box::new(parsed));
         }
+        Ok(self)
     }
 
     /// Retrieves a mutable reference to the application context associated with the Connection.
@@ -1083,9 +1727,151 @@ impl Connection {
     /// To set a context on the connection, use [`Self::set_application_context()`]. To retrieve an
     /// immutable reference to the context, use [`Self::application_context()`].
     pub fn application_context_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
-        match self.context_mut().app_context.as_mut() {
-            None => None,
-            Some(app_context) => app_context.downcast_mut::<T>(),
+        self.context_mut()
+            .app_context
+            .get_mut(&(TypeId::of::<T>(), None))
+            .and_then(|app_context| app_context.downcast_mut::<T>())
+    }
+
+    /// Temporarily installs an application context of type T, shadowing any prior value of the
+    /// same type, and returns a [`ContextGuard`] that restores the previous value when dropped.
+    ///
+    /// The guard borrows the connection exclusively and dereferences to it, so the shadowed
+    /// `value` is observed through the guard (e.g. `guard.application_context()`). When the guard
+    /// is dropped the previous value (if any) is restored, or the slot is emptied. This lets a
+    /// callback that re-enters during renegotiation or a nested handshake phase override the
+    /// context for the duration of a scope without manually saving and restoring it, and unwinds
+    /// correctly even on panic or early return.
+    ///
+    /// Guards must be dropped in LIFO order (the most recently pushed guard dropped first); this
+    /// is debug-asserted on drop.
+    pub fn push_application_context<T: Send + Sync + 'static>(
+        &mut self,
+        value: T,
+    ) -> ContextGuard<'_, T> {
+        let context = self.context_mut();
+        context.context_stack_depth += 1;
+        let depth = context.context_stack_depth;
+        let previous = context
+            .app_context
+            .insert((TypeId::of::<T>(), None), Box::new(value));
+        ContextGuard {
+            conn: self,
+            previous,
+            depth,
+            value: PhantomData,
+        }
+    }
+
+    /// Removes the application context of type T from the Connection and returns ownership of it.
+    ///
+    /// If an application context of type T hasn't been set, None is returned. This only affects
+    /// the unkeyed single-value slot; keyed values set via
+    /// [`Self::set_application_context_keyed()`] are left untouched.
+    pub fn take_application_context<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.context_mut()
+            .app_context
+            .remove(&(TypeId::of::<T>(), None))
+            // downcast() returns the Box back as its Err when the type doesn't match, but the
+            // TypeId key guarantees the stored value is a T, so the downcast always succeeds.
+            .and_then(|app_context| app_context.downcast::<T>().ok())
+            .map(|app_context| *app_context)
+    }
+
+    /// Associates an application context with the Connection under a string `key`, allowing
+    /// several distinct values of the same type T to be stored at once.
+    ///
+    /// Unlike [`Self::set_application_context()`], which allows a single value per type, this keys
+    /// the value by both its type and `key`, so that (for example) two `String`s describing a
+    /// "tenant" and a "route" can coexist. The unkeyed API is equivalent to the `None`-key entry
+    /// and is unaffected by keyed values.
+    ///
+    /// This API will override an existing keyed application context of the same type and key.
+    pub fn set_application_context_keyed<T: Send + Sync + 'static>(
+        &mut self,
+        key: &'static str,
+        app_context: T,
+    ) {
+        self.context_mut()
+            .app_context
+            .insert((TypeId::of::<T>(), Some(key)), Box::new(app_context));
+    }
+
+    /// Retrieves a reference to the keyed application context stored under `key`.
+    ///
+    /// If no value of type T has been set for `key` via
+    /// [`Self::set_application_context_keyed()`], None will be returned.
+    pub fn application_context_keyed<T: Send + Sync + 'static>(
+        &self,
+        key: &'static str,
+    ) -> Option<&T> {
+        self.context()
+            .app_context
+            .get(&(TypeId::of::<T>(), Some(key)))
+            .and_then(|app_context| app_context.downcast_ref::<T>())
+    }
+
+    /// Retrieves a mutable reference to the keyed application context stored under `key`.
+    ///
+    /// If no value of type T has been set for `key` via
+    /// [`Self::set_application_context_keyed()`], None will be returned.
+    pub fn application_context_keyed_mut<T: Send + Sync + 'static>(
+        &mut self,
+        key: &'static str,
+    ) -> Option<&mut T> {
+        self.context_mut()
+            .app_context
+            .get_mut(&(TypeId::of::<T>(), Some(key)))
+            .and_then(|app_context| app_context.downcast_mut::<T>())
+    }
+}
+
+/// An RAII guard returned by [`Connection::push_application_context()`] that restores the
+/// previously-stored application context of type T when dropped.
+///
+/// Guards must be dropped in LIFO order. The guard holds the `&mut Connection` it was created from
+/// and dereferences to it, so the connection stays usable through the guard while the previous
+/// value is shadowed, without handing out an aliasing reference into the context store.
+pub struct ContextGuard<'a, T: Send + Sync + 'static> {
+    conn: &'a mut Connection,
+    previous: Option<Box<dyn Any + Send + Sync>>,
+    depth: usize,
+    value: PhantomData<T>,
+}
+
+impl<T: Send + Sync + 'static> Deref for ContextGuard<'_, T> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl<T: Send + Sync + 'static> DerefMut for ContextGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn
+    }
+}
+
+impl<T: Send + Sync + 'static> Drop for ContextGuard<'_, T> {
+    fn drop(&mut self) {
+        let depth = self.depth;
+        let previous = self.previous.take();
+        let context = self.conn.context_mut();
+        debug_assert_eq!(
+            context.context_stack_depth, depth,
+            "ContextGuards must be dropped in LIFO order"
+        );
+        context.context_stack_depth -= 1;
+        match previous {
+            Some(previous) => {
+                context
+                    .app_context
+                    .insert((TypeId::of::<T>(), None), previous);
+            }
+            None => {
+                context.app_context.remove(&(TypeId::of::<T>(), None));
+            }
         }
     }
 }
@@ -1096,7 +1882,21 @@ struct Context {
     async_callback: Option<AsyncCallback>,
     verify_host_callback: Option<Box<dyn VerifyHostNameCallback>>,
     connection_initialized: bool,
-    app_context: Option<Box<dyn Any + Send + Sync>>,
+    app_context: HashMap<(TypeId, Option<&'static str>), Box<dyn Any + Send + Sync>>,
+    app_context_env: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    app_context_default: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    context_stack_depth: usize,
+    session_ticket_callback: Option<Box<dyn SessionTicketCallback>>,
+    key_log_callback: Option<Box<dyn KeyLogCallback>>,
+    session_cache: Option<Box<dyn SessionCache>>,
+    #[cfg(feature = "unstable-custom-extensions")]
+    extension_handlers: Vec<Box<dyn ExtensionHandler>>,
+    #[cfg(feature = "quic")]
+    quic_secret_callback: Option<Box<dyn QuicSecretCallback>>,
+    #[cfg(feature = "unstable-ktls")]
+    ktls_send_enabled: bool,
+    #[cfg(feature = "unstable-ktls")]
+    ktls_recv_enabled: bool,
 }
 
 impl Context {
@@ -1107,11 +1907,65 @@ impl Context {
             async_callback: None,
             verify_host_callback: None,
             connection_initialized: false,
-            app_context: None,
+            app_context: HashMap::new(),
+            app_context_env: HashMap::new(),
+            app_context_default: HashMap::new(),
+            context_stack_depth: 0,
+            session_ticket_callback: None,
+            key_log_callback: None,
+            session_cache: None,
+            #[cfg(feature = "unstable-custom-extensions")]
+            extension_handlers: Vec::new(),
+            #[cfg(feature = "quic")]
+            quic_secret_callback: None,
+            #[cfg(feature = "unstable-ktls")]
+            ktls_send_enabled: false,
+            #[cfg(feature = "unstable-ktls")]
+            ktls_recv_enabled: false,
         }
     }
 }
 
+/// The stage of the TLS1.3 key schedule a secret was derived for.
+///
+/// Delivered to a [`QuicSecretCallback`] so a QUIC stack can install the
+/// corresponding packet-protection keys.
+#[cfg(feature = "quic")]
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SecretType {
+    ClientHandshake,
+    ServerHandshake,
+    ClientApplication,
+    ServerApplication,
+}
+
+#[cfg(feature = "quic")]
+impl TryFrom<s2n_secret_type::Type> for SecretType {
+    type Error = Error;
+
+    fn try_from(input: s2n_secret_type::Type) -> Result<Self, Self::Error> {
+        match input {
+            s2n_secret_type::CLIENT_HANDSHAKE_TRAFFIC_SECRET => Ok(SecretType::ClientHandshake),
+            s2n_secret_type::SERVER_HANDSHAKE_TRAFFIC_SECRET => Ok(SecretType::ServerHandshake),
+            s2n_secret_type::CLIENT_APPLICATION_TRAFFIC_SECRET => Ok(SecretType::ClientApplication),
+            s2n_secret_type::SERVER_APPLICATION_TRAFFIC_SECRET => Ok(SecretType::ServerApplication),
+            _ => Err(Error::INVALID_INPUT),
+        }
+    }
+}
+
+/// A callback that receives each TLS1.3 traffic secret as it is derived.
+///
+/// The secret is handed to user code together with the handshake stage it
+/// belongs to and the negotiated cipher suite, which together are enough for a
+/// QUIC layer to derive its packet-protection keys. Secrets are highly
+/// sensitive and must not be logged or persisted in plaintext.
+#[cfg(feature = "quic")]
+pub trait QuicSecretCallback: 'static + Send + Sync {
+    fn on_secret(&mut self, secret_type: SecretType, secret: &[u8], cipher_suite: &str);
+}
+
 #[cfg(feature = "quic")]
 impl Connection {
     pub fn enable_quic(&mut self) -> Result<&mut Self, Error> {
@@ -1159,6 +2013,64 @@ impl Connection {
         Ok(self)
     }
 
+    /// Registers a callback that receives each TLS1.3 traffic secret as it is
+    /// derived during the handshake.
+    ///
+    /// This is the safe, owned counterpart to [`Self::set_secret_callback`]: the
+    /// handler is stored on the connection [`Context`] and dispatched from an
+    /// FFI trampoline, so no raw context pointer needs to be managed by the
+    /// caller. It is the mechanism a QUIC stack uses to learn the secrets it
+    /// needs to protect and unprotect packets.
+    pub fn set_quic_secret_callback<T: 'static + QuicSecretCallback>(
+        &mut self,
+        handler: T,
+    ) -> Result<&mut Self, Error> {
+        unsafe extern "C" fn quic_secret_cb(
+            context: *mut c_void,
+            conn: *mut s2n_connection,
+            secret_type: s2n_secret_type::Type,
+            secret: *mut u8,
+            secret_size: u8,
+        ) -> libc::c_int {
+            let context = &mut *(context as *mut Context);
+            let handler = match context.quic_secret_callback.as_mut() {
+                Some(handler) => handler,
+                None => return -1,
+            };
+
+            // s2n-tls may derive secrets (such as early traffic secrets) that
+            // don't map to a stage we model; skip those rather than fail.
+            let secret_type = match SecretType::try_from(secret_type) {
+                Ok(secret_type) => secret_type,
+                Err(_) => return 0,
+            };
+            let secret = core::slice::from_raw_parts(secret, secret_size as usize);
+
+            // The cipher suite is negotiated before any traffic secret is
+            // derived, so this is always available here.
+            let cipher = s2n_connection_get_cipher(conn);
+            let cipher_suite = if cipher.is_null() {
+                ""
+            } else {
+                CStr::from_ptr(cipher).to_str().unwrap_or("")
+            };
+
+            handler.on_secret(secret_type, secret, cipher_suite);
+            0
+        }
+
+        self.context_mut().quic_secret_callback = Some(Box::new(handler));
+        unsafe {
+            s2n_connection_set_secret_callback(
+                self.connection.as_ptr(),
+                Some(quic_secret_cb),
+                self.context_mut() as *mut Context as *mut c_void,
+            )
+            .into_result()
+        }?;
+        Ok(self)
+    }
+
     pub fn quic_process_post_handshake_message(&mut self) -> Result<&mut Self, Error> {
         let mut blocked = s2n_blocked_status::NOT_BLOCKED;
         unsafe {
@@ -1289,4 +2201,133 @@ mod tests {
         // Retrieving the correct type succeeds.
         assert!(connection.application_context::<u32>().is_some());
     }
+
+    /// Test that application context layers resolve in precedence order.
+    #[test]
+    fn test_app_context_layers() {
+        let mut connection = Connection::new_server();
+
+        // With nothing set, the default is returned.
+        connection.set_application_context_default(10u32);
+        assert_eq!(*connection.application_context::<u32>().unwrap(), 10);
+
+        // The environment layer overrides the default.
+        let var = "S2N_TEST_APP_CONTEXT_LAYER";
+        std::env::set_var(var, "20");
+        connection.application_context_from_env::<u32>(var).unwrap();
+        assert_eq!(*connection.application_context::<u32>().unwrap(), 20);
+        std::env::remove_var(var);
+
+        // An explicitly-set value overrides both.
+        connection.set_application_context(30u32);
+        assert_eq!(*connection.application_context::<u32>().unwrap(), 30);
+    }
+
+    /// Test that a present-but-unparseable env var is surfaced as an error.
+    #[test]
+    fn test_app_context_env_parse_error() {
+        let mut connection = Connection::new_server();
+
+        let var = "S2N_TEST_APP_CONTEXT_BAD";
+        std::env::set_var(var, "not-a-number");
+        let result = connection.application_context_from_env::<u32>(var);
+        std::env::remove_var(var);
+
+        assert!(result.is_err());
+    }
+
+    /// Test that a pushed context shadows and then restores the previous value.
+    #[test]
+    fn test_app_context_scoped() {
+        let mut connection = Connection::new_server();
+
+        connection.set_application_context(1u32);
+        assert_eq!(*connection.application_context::<u32>().unwrap(), 1);
+
+        {
+            // The guard borrows the connection exclusively and derefs to it, so
+            // the shadowed value is observed through the guard.
+            let mut guard = connection.push_application_context(2u32);
+            assert_eq!(*guard.application_context::<u32>().unwrap(), 2);
+
+            {
+                let inner = guard.push_application_context(3u32);
+                assert_eq!(*inner.application_context::<u32>().unwrap(), 3);
+            }
+
+            // The inner guard restored the value pushed by the outer guard.
+            assert_eq!(*guard.application_context::<u32>().unwrap(), 2);
+        }
+
+        // The outer guard restored the original value.
+        assert_eq!(*connection.application_context::<u32>().unwrap(), 1);
+    }
+
+    /// Test that a pushed context with no prior value is removed on drop.
+    #[test]
+    fn test_app_context_scoped_restores_empty() {
+        let mut connection = Connection::new_server();
+
+        {
+            let guard = connection.push_application_context("scoped".to_string());
+            assert_eq!(guard.application_context::<String>().unwrap(), "scoped");
+        }
+
+        assert!(connection.application_context::<String>().is_none());
+    }
+
+    /// Test that an application context can be taken back out by value.
+    #[test]
+    fn test_app_context_take() {
+        let mut connection = Connection::new_server();
+
+        // Taking before anything is set returns None.
+        assert!(connection.take_application_context::<String>().is_none());
+
+        connection.set_application_context("owned".to_string());
+
+        let taken = connection.take_application_context::<String>();
+        assert_eq!(taken.unwrap(), "owned");
+
+        // Once taken, the context is gone.
+        assert!(connection.application_context::<String>().is_none());
+    }
+
+    /// Test that several values of the same type can be stored under distinct keys.
+    #[test]
+    fn test_app_context_keyed() {
+        let mut connection = Connection::new_server();
+
+        connection.set_application_context_keyed("tenant", "acme".to_string());
+        connection.set_application_context_keyed("route", "/login".to_string());
+
+        assert_eq!(
+            connection
+                .application_context_keyed::<String>("tenant")
+                .unwrap(),
+            "acme"
+        );
+        assert_eq!(
+            connection
+                .application_context_keyed::<String>("route")
+                .unwrap(),
+            "/login"
+        );
+
+        // A key that wasn't set returns None.
+        assert!(connection
+            .application_context_keyed::<String>("missing")
+            .is_none());
+
+        // The keyed values don't collide with the unkeyed single-value slot.
+        assert!(connection.application_context::<String>().is_none());
+        connection.set_application_context("unkeyed".to_string());
+        assert_eq!(connection.application_context::<String>().unwrap(), "unkeyed");
+        assert_eq!(
+            connection
+                .application_context_keyed::<String>("tenant")
+                .unwrap(),
+            "acme"
+        );
+    }
 }
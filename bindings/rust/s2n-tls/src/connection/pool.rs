@@ -0,0 +1,170 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{config::Config, connection::Connection, enums::Mode, error::Error};
+use core::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// A pool of reusable [`Connection`] handles sharing a single [`Mode`] and [`Config`].
+///
+/// Reusing a handle via [`Connection::wipe`] avoids the allocation and
+/// handshake-buffer setup that [`Connection::new`] performs, which matters for
+/// high-churn servers that open and close many short-lived connections. A pool
+/// hands out handles through the [`PooledConnection`] guard, which wipes the
+/// handle and returns it to the pool on drop.
+pub struct ConnectionPool {
+    mode: Mode,
+    config: Config,
+    max_size: usize,
+    release_buffers: bool,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    /// Creates a pool that hands out connections of the given `mode` associated
+    /// with `config`, using the default settings (no idle limit, buffers
+    /// retained). Use [`ConnectionPool::builder`] to override them.
+    pub fn new(mode: Mode, config: Config) -> Arc<Self> {
+        Self::builder(mode, config).build()
+    }
+
+    /// Starts building a pool for the given `mode` and `config`.
+    pub fn builder(mode: Mode, config: Config) -> Builder {
+        Builder {
+            mode,
+            config,
+            max_size: usize::MAX,
+            release_buffers: false,
+        }
+    }
+
+    /// Returns the number of idle connections currently parked in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    /// Checks out a connection, reusing an idle handle if one is available and
+    /// otherwise allocating a new one.
+    pub fn checkout(self: &Arc<Self>) -> Result<PooledConnection, Error> {
+        if let Some(pooled) = self.try_checkout() {
+            return Ok(pooled);
+        }
+        let connection = self.new_connection()?;
+        Ok(self.guard(connection))
+    }
+
+    /// Checks out an idle connection without allocating.
+    ///
+    /// Returns `None` if the pool has no parked connections.
+    pub fn try_checkout(self: &Arc<Self>) -> Option<PooledConnection> {
+        let connection = self.idle.lock().unwrap().pop()?;
+        Some(self.guard(connection))
+    }
+
+    /// Builds a fresh connection configured for this pool.
+    fn new_connection(&self) -> Result<Connection, Error> {
+        let mut connection = Connection::new(self.mode);
+        connection.set_config(self.config.clone())?;
+        Ok(connection)
+    }
+
+    fn guard(self: &Arc<Self>, connection: Connection) -> PooledConnection {
+        PooledConnection {
+            connection: Some(connection),
+            pool: Arc::clone(self),
+        }
+    }
+
+    /// Wipes `connection` and parks it for reuse, unless the pool is full.
+    fn checkin(&self, mut connection: Connection) {
+        // A wiped connection drops its config, so restore the pool's config and
+        // optionally release its buffers before parking it.
+        if connection.wipe().is_err() {
+            return;
+        }
+        if self.release_buffers && connection.release_buffers().is_err() {
+            return;
+        }
+        if connection.set_config(self.config.clone()).is_err() {
+            return;
+        }
+
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.max_size {
+            idle.push(connection);
+        }
+    }
+}
+
+/// Builder for a [`ConnectionPool`].
+///
+/// Because a pool is shared through an [`Arc`], its settings are fixed at
+/// construction rather than mutated through the handle.
+pub struct Builder {
+    mode: Mode,
+    config: Config,
+    max_size: usize,
+    release_buffers: bool,
+}
+
+impl Builder {
+    /// Sets the maximum number of idle connections the pool will retain.
+    ///
+    /// Connections returned while the pool is already at capacity are dropped
+    /// instead of being parked. Defaults to no limit.
+    pub fn set_max_size(&mut self, max_size: usize) -> &mut Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets whether the in/out buffers are released via
+    /// [`Connection::release_buffers`] before a handle is returned to the pool.
+    ///
+    /// This trades cheaper reuse for a smaller memory footprint per idle handle.
+    pub fn set_release_buffers(&mut self, release_buffers: bool) -> &mut Self {
+        self.release_buffers = release_buffers;
+        self
+    }
+
+    /// Builds the shared [`ConnectionPool`].
+    pub fn build(&self) -> Arc<ConnectionPool> {
+        Arc::new(ConnectionPool {
+            mode: self.mode,
+            config: self.config.clone(),
+            max_size: self.max_size,
+            release_buffers: self.release_buffers,
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+/// An RAII guard around a [`Connection`] checked out from a [`ConnectionPool`].
+///
+/// The connection is returned to the pool when the guard is dropped. Deref to
+/// the inner [`Connection`] to use it.
+pub struct PooledConnection {
+    connection: Option<Connection>,
+    pool: Arc<ConnectionPool>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.connection.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.checkin(connection);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use core::{convert::TryInto, fmt, ptr::NonNull, task::Poll};
 use errno::{errno, Errno};
 use libc::c_char;
 use s2n_tls_sys::*;
-use std::{convert::TryFrom, ffi::CStr};
+use std::{convert::TryFrom, error::Error as StdError, ffi::CStr, sync::Arc};
 
 #[non_exhaustive]
 #[derive(Debug, PartialEq)]
@@ -44,14 +44,299 @@ impl From<libc::c_int> for ErrorType {
     }
 }
 
+/// Generates the [`ErrorCode`] enum and a name-based lookup from a table of
+/// `Variant => "S2N_ERR_NAME"` pairs.
+///
+/// The names are matched against [`Error::name()`] (i.e. `s2n_strerror_name`),
+/// which is stable across s2n-tls versions, rather than the raw status code,
+/// whose numeric value is an internal implementation detail.
+macro_rules! error_codes {
+    ($($variant:ident => $name:literal),+ $(,)?) => {
+        /// A stable, matchable identity for an [`Error`].
+        ///
+        /// Unlike [`Error::name()`], which returns a human-oriented `&str`, this
+        /// lets downstream code branch on a specific failure without comparing
+        /// display strings. It is `#[non_exhaustive]` because s2n-tls adds error
+        /// names over time.
+        #[non_exhaustive]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum ErrorCode {
+            /// The error did not originate from s2n-tls (e.g. an `InvalidInput`
+            /// bindings error), so it has no s2n status code.
+            None,
+            $(
+                #[doc = concat!("`", $name, "`")]
+                $variant,
+            )+
+            /// An s2n-tls error whose name is not yet mapped to an [`ErrorCode`].
+            Unknown,
+        }
+
+        impl ErrorCode {
+            fn from_name(name: &str) -> Self {
+                match name {
+                    $($name => ErrorCode::$variant,)+
+                    _ => ErrorCode::Unknown,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    Io => "S2N_ERR_IO",
+    Closed => "S2N_ERR_CLOSED",
+    IoBlocked => "S2N_ERR_IO_BLOCKED",
+    AsyncBlocked => "S2N_ERR_ASYNC_BLOCKED",
+    Alert => "S2N_ERR_ALERT",
+    Cancelled => "S2N_ERR_CANCELLED",
+    BadMessage => "S2N_ERR_BAD_MESSAGE",
+    Encrypt => "S2N_ERR_ENCRYPT",
+    Decrypt => "S2N_ERR_DECRYPT",
+    CipherNotSupported => "S2N_ERR_CIPHER_NOT_SUPPORTED",
+    NoApplicationProtocol => "S2N_ERR_NO_APPLICATION_PROTOCOL",
+    ProtocolVersionUnsupported => "S2N_ERR_PROTOCOL_VERSION_UNSUPPORTED",
+    NoCertificateInPem => "S2N_ERR_NO_CERTIFICATE_IN_PEM",
+    CertUntrusted => "S2N_ERR_CERT_UNTRUSTED",
+    CertRevoked => "S2N_ERR_CERT_REVOKED",
+    CertExpired => "S2N_ERR_CERT_EXPIRED",
+    CertInvalid => "S2N_ERR_CERT_INVALID",
+    CertTypeUnsupported => "S2N_ERR_CERT_TYPE_UNSUPPORTED",
+    CertUnhandledCriticalExtension => "S2N_ERR_CERT_UNHANDLED_CRITICAL_EXTENSION",
+    EchConfigListDecode => "S2N_ERR_ECH_CONFIG_LIST_DECODE",
+    EchConfigListEncode => "S2N_ERR_ECH_CONFIG_LIST_ENCODE",
+    EchRejected => "S2N_ERR_ECH_REJECTED",
+}
+
 #[derive(Clone, PartialEq)]
 pub enum Context {
     InvalidInput,
     Code(s2n_status_code::Type, Errno),
 }
 
-#[derive(Clone, PartialEq)]
-pub struct Error(Context);
+/// The rich, heap-allocated payload for the uncommon case where an error
+/// carries a source and/or ECH retry config alongside its [`Context`].
+///
+/// The common inline cases ([`Context::InvalidInput`] and a bare
+/// [`Context::Code`]) never allocate; only errors that gain an attached payload
+/// are boxed. See [`repr_bitpacked`].
+#[derive(Clone)]
+struct BoxedError {
+    context: Context,
+    // The originating error (e.g. the `std::io::Error` from a failed send/recv
+    // callback) that s2n-tls surfaced as this error. Arc-wrapped so it stays
+    // `Clone` even though a `dyn Error` is not. See meli's `set_source`.
+    source: Option<Arc<dyn StdError + Send + Sync + 'static>>,
+    // The `retry_configs` bytes captured when a server rejects ECH and signals
+    // a retry, so a client can pull them out of the same error and reconnect.
+    #[cfg(feature = "unstable-ech")]
+    ech_retry_config: Option<Arc<[u8]>>,
+}
+
+impl BoxedError {
+    fn new(context: Context) -> Self {
+        BoxedError {
+            context,
+            source: None,
+            #[cfg(feature = "unstable-ech")]
+            ech_retry_config: None,
+        }
+    }
+}
+
+/// The decoded contents of an [`Error`], borrowed from its representation.
+enum ErrorData<'a> {
+    InvalidInput,
+    Code(s2n_status_code::Type, Errno),
+    Boxed(&'a BoxedError),
+}
+
+/// `Error` is returned from every [`Fallible::into_result`] and
+/// [`Pollable::into_poll`], i.e. on every non-blocking read/write poll, so its
+/// size and copy cost matter on hot loops. On 64-bit targets it is therefore
+/// stored as a single pointer-width word via [`repr_bitpacked`], which keeps the
+/// common inline error one word wide while still allowing the richer boxed
+/// payloads. Other targets use the plain-enum [`repr_unpacked`] fallback, which
+/// is wider than one word.
+pub struct Error {
+    repr: Repr,
+}
+
+#[cfg(target_pointer_width = "64")]
+use repr_bitpacked::Repr;
+#[cfg(not(target_pointer_width = "64"))]
+use repr_unpacked::Repr;
+
+/// Bit-packed single-word representation for 64-bit targets, modeled on the
+/// technique std uses for `io::Error`.
+///
+/// The low 2 tag bits select the variant; the remaining 62 bits hold either an
+/// inline `(status_code, errno)` pair (both small `i32`s) or a pointer to a
+/// heap-allocated [`BoxedError`] (at least 4-byte aligned, so the tag bits are
+/// free).
+#[cfg(target_pointer_width = "64")]
+mod repr_bitpacked {
+    use super::{BoxedError, ErrorData, Errno};
+    use s2n_tls_sys::s2n_status_code;
+
+    const TAG_MASK: usize = 0b11;
+    const TAG_INVALID_INPUT: usize = 0b00;
+    const TAG_CODE: usize = 0b01;
+    const TAG_BOXED: usize = 0b10;
+
+    // Split the 62 non-tag bits evenly between the status code and errno. Both
+    // are small non-negative `i32`s in practice, so 31 bits each is ample.
+    const FIELD_BITS: usize = 31;
+    const FIELD_MASK: usize = (1 << FIELD_BITS) - 1;
+
+    pub(super) struct Repr(usize);
+
+    impl Repr {
+        pub(super) const fn new_invalid_input() -> Self {
+            Repr(TAG_INVALID_INPUT)
+        }
+
+        pub(super) fn new_code(code: s2n_status_code::Type, errno: Errno) -> Self {
+            let code = (code as u32 as usize) & FIELD_MASK;
+            let errno = (errno.0 as u32 as usize) & FIELD_MASK;
+            Repr((errno << (2 + FIELD_BITS)) | (code << 2) | TAG_CODE)
+        }
+
+        pub(super) fn new_boxed(boxed: Box<BoxedError>) -> Self {
+            let ptr = Box::into_raw(boxed) as usize;
+            debug_assert_eq!(
+                ptr & TAG_MASK,
+                0,
+                "BoxedError pointer must leave the tag bits free"
+            );
+            Repr(ptr | TAG_BOXED)
+        }
+
+        pub(super) fn data(&self) -> ErrorData<'_> {
+            match self.0 & TAG_MASK {
+                TAG_INVALID_INPUT => ErrorData::InvalidInput,
+                TAG_CODE => {
+                    let code = ((self.0 >> 2) & FIELD_MASK) as u32 as s2n_status_code::Type;
+                    let errno = ((self.0 >> (2 + FIELD_BITS)) & FIELD_MASK) as u32 as i32;
+                    ErrorData::Code(code, Errno(errno))
+                }
+                _ => {
+                    let ptr = (self.0 & !TAG_MASK) as *const BoxedError;
+                    // Safety: the pointer came from `Box::into_raw` in `new_boxed`
+                    // and is only freed in `Drop`, so it is valid for `&self`.
+                    ErrorData::Boxed(unsafe { &*ptr })
+                }
+            }
+        }
+
+        fn boxed_ptr(&self) -> Option<*mut BoxedError> {
+            if self.0 & TAG_MASK == TAG_BOXED {
+                Some((self.0 & !TAG_MASK) as *mut BoxedError)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl Clone for Repr {
+        fn clone(&self) -> Self {
+            match self.data() {
+                ErrorData::InvalidInput => Repr::new_invalid_input(),
+                ErrorData::Code(code, errno) => Repr::new_code(code, errno),
+                ErrorData::Boxed(boxed) => Repr::new_boxed(Box::new(boxed.clone())),
+            }
+        }
+    }
+
+    impl Drop for Repr {
+        fn drop(&mut self) {
+            if let Some(ptr) = self.boxed_ptr() {
+                // Safety: reconstruct and free the `Box` leaked in `new_boxed`,
+                // exactly once, when the owning `Error` is dropped.
+                drop(unsafe { Box::from_raw(ptr) });
+            }
+        }
+    }
+}
+
+/// Plain-enum fallback for non-64-bit targets, where bit-packing a pointer and
+/// two `i32`s into one word isn't guaranteed to fit. The public [`Error`] API is
+/// identical to the bit-packed representation.
+#[cfg(not(target_pointer_width = "64"))]
+mod repr_unpacked {
+    use super::{BoxedError, ErrorData, Errno};
+    use s2n_tls_sys::s2n_status_code;
+
+    #[derive(Clone)]
+    enum Kind {
+        InvalidInput,
+        Code(s2n_status_code::Type, Errno),
+        Boxed(Box<BoxedError>),
+    }
+
+    #[derive(Clone)]
+    pub(super) struct Repr(Kind);
+
+    impl Repr {
+        pub(super) const fn new_invalid_input() -> Self {
+            Repr(Kind::InvalidInput)
+        }
+
+        pub(super) fn new_code(code: s2n_status_code::Type, errno: Errno) -> Self {
+            Repr(Kind::Code(code, errno))
+        }
+
+        pub(super) fn new_boxed(boxed: Box<BoxedError>) -> Self {
+            Repr(Kind::Boxed(boxed))
+        }
+
+        pub(super) fn data(&self) -> ErrorData<'_> {
+            match &self.0 {
+                Kind::InvalidInput => ErrorData::InvalidInput,
+                Kind::Code(code, errno) => ErrorData::Code(*code, *errno),
+                Kind::Boxed(boxed) => ErrorData::Boxed(boxed),
+            }
+        }
+    }
+}
+
+impl Error {
+    /// Returns the s2n [`Context`] for this error, regardless of whether it is
+    /// stored inline or behind a boxed payload.
+    fn context(&self) -> Context {
+        match self.repr.data() {
+            ErrorData::InvalidInput => Context::InvalidInput,
+            ErrorData::Code(code, errno) => Context::Code(code, errno),
+            ErrorData::Boxed(boxed) => boxed.context.clone(),
+        }
+    }
+
+    /// Converts this error into an owned [`BoxedError`] so a payload can be
+    /// attached, allocating only if it wasn't already boxed.
+    fn into_boxed(self) -> Box<BoxedError> {
+        match self.repr.data() {
+            ErrorData::InvalidInput => Box::new(BoxedError::new(Context::InvalidInput)),
+            ErrorData::Code(code, errno) => Box::new(BoxedError::new(Context::Code(code, errno))),
+            ErrorData::Boxed(boxed) => Box::new(boxed.clone()),
+        }
+    }
+}
+
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        Error {
+            repr: self.repr.clone(),
+        }
+    }
+}
+
+impl PartialEq for Error {
+    // The source is diagnostic metadata; two errors are equal when their s2n
+    // context matches, mirroring the behavior before a source was attachable.
+    fn eq(&self, other: &Self) -> bool {
+        self.context() == other.context()
+    }
+}
 
 pub trait Fallible {
     type Output;
@@ -151,7 +436,9 @@ impl Error {
     // Keep this naming.
     // TODO: Update this + all references to all upper case.
     #[allow(non_upper_case_globals)]
-    pub(crate) const InvalidInput: Error = Self(Context::InvalidInput);
+    pub(crate) const InvalidInput: Error = Self {
+        repr: Repr::new_invalid_input(),
+    };
 
     pub fn new<T: Fallible>(value: T) -> Result<T::Output, Self> {
         value.into_result()
@@ -168,12 +455,53 @@ impl Error {
             //# an error: s2n_errno = S2N_ERR_T_OK
             *s2n_errno = s2n_error_type::OK as _;
 
-            Self(Context::Code(code, errno()))
+            Self {
+                repr: Repr::new_code(code, errno()),
+            }
+        }
+    }
+
+    /// Enriches an error captured during negotiation with the server's ECH
+    /// `retry_configs` when the failure is an ECH rejection.
+    ///
+    /// This is called on the negotiate path, where a connection handle is
+    /// available to fetch the fresh config bytes; [`Self::capture()`] alone can
+    /// only read the thread-local status code.
+    #[cfg(feature = "unstable-ech")]
+    pub(crate) fn with_ech_retry_config(self, connection: *mut s2n_connection) -> Self {
+        if self.error_code() != ErrorCode::EchRejected {
+            return self;
+        }
+        let mut boxed = self.into_boxed();
+        unsafe {
+            let mut data: *const u8 = core::ptr::null();
+            let mut len: u32 = 0;
+            let fetched =
+                s2n_connection_get_ech_retry_configs(connection, &mut data, &mut len)
+                    .into_result();
+            if fetched.is_ok() && !data.is_null() {
+                let bytes = core::slice::from_raw_parts(data, len as usize);
+                boxed.ech_retry_config = Some(Arc::from(bytes.to_vec().into_boxed_slice()));
+            }
+        }
+        Error {
+            repr: Repr::new_boxed(boxed),
+        }
+    }
+
+    /// Attaches `source` as the originating error behind this one, so that
+    /// [`std::error::Error::source`] and [`Self::chain_display`] can walk down to
+    /// the transport or callback failure that actually occurred.
+    pub fn with_source(self, source: impl StdError + Send + Sync + 'static) -> Self {
+        let mut boxed = self.into_boxed();
+        boxed.source = Some(Arc::new(source));
+        Error {
+            repr: Repr::new_boxed(boxed),
         }
     }
 
     pub fn name(&self) -> &'static str {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => "InvalidInput",
             Context::Code(code, _) => unsafe {
                 // Safety: we assume the string has a valid encoding coming from s2n
@@ -183,7 +511,7 @@ impl Error {
     }
 
     pub fn message(&self) -> &'static str {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => "A parameter was incorrect",
             Context::Code(code, _) => unsafe {
                 // Safety: we assume the string has a valid encoding coming from s2n
@@ -193,7 +521,7 @@ impl Error {
     }
 
     pub fn debug(&self) -> Option<&'static str> {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => None,
             Context::Code(code, _) => unsafe {
                 let debug_info = s2n_strerror_debug(code, core::ptr::null());
@@ -213,14 +541,14 @@ impl Error {
     }
 
     pub fn kind(&self) -> ErrorType {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => ErrorType::UsageError,
             Context::Code(code, _) => unsafe { ErrorType::from(s2n_error_get_type(code)) },
         }
     }
 
     pub fn source(&self) -> ErrorSource {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => ErrorSource::Bindings,
             Context::Code(_, _) => ErrorSource::Library,
         }
@@ -229,6 +557,87 @@ impl Error {
     pub fn is_retryable(&self) -> bool {
         matches!(self.kind(), ErrorType::Blocked)
     }
+
+    /// Returns the raw s2n-tls status code this error captured, if any.
+    ///
+    /// Bindings-originated errors (see [`ErrorSource::Bindings`]) have no status
+    /// code and return None. The numeric value is an s2n-tls implementation
+    /// detail; prefer [`Self::error_code()`] for matching on a specific failure.
+    pub fn code(&self) -> Option<s2n_status_code::Type> {
+        match self.context() {
+            Context::InvalidInput => None,
+            Context::Code(code, _) => Some(code),
+        }
+    }
+
+    /// Returns a stable, matchable [`ErrorCode`] for this error.
+    ///
+    /// This lets callers branch on a specific s2n-tls failure
+    /// (`match err.error_code() { ErrorCode::CipherNotSupported => ... }`)
+    /// without comparing the display strings from [`Self::name()`].
+    pub fn error_code(&self) -> ErrorCode {
+        match self.context() {
+            Context::InvalidInput => ErrorCode::None,
+            Context::Code(_, _) => ErrorCode::from_name(self.name()),
+        }
+    }
+
+    /// Returns the ECH `retry_configs` bytes attached to this error, if the
+    /// failure was an ECH rejection that carried a retry config.
+    ///
+    /// A client can detect the rejection via [`Self::error_code()`] and pull the
+    /// fresh config blob out of the same error to transparently reconnect,
+    /// without a separate out-of-band API on the connection.
+    #[cfg(feature = "unstable-ech")]
+    pub fn ech_retry_config(&self) -> Option<&[u8]> {
+        match self.repr.data() {
+            ErrorData::Boxed(boxed) => boxed.ech_retry_config.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns a [`Display`](fmt::Display) wrapper that renders this error and
+    /// every [`source()`](StdError::source) ancestor as an indented chain.
+    ///
+    /// Unlike the plain [`Display`](fmt::Display) impl, which prints only
+    /// [`message()`](Self::message), this collects the s2n name, [`ErrorType`],
+    /// message, and (when present) debug string and errno into a single
+    /// log-friendly string, then follows the source chain down to the
+    /// originating transport/callback error.
+    pub fn chain_display(&self) -> impl fmt::Display + '_ {
+        ErrorChainDisplay(self)
+    }
+}
+
+/// See [`Error::chain_display()`].
+pub struct ErrorChainDisplay<'a>(&'a Error);
+
+impl fmt::Display for ErrorChainDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let err = self.0;
+        write!(f, "{} ({:?}): {}", err.name(), err.kind(), err.message())?;
+        if let Some(debug) = err.debug() {
+            write!(f, " [{debug}]")?;
+        }
+        if let Context::Code(_, errno) = err.context() {
+            write!(f, " (errno: {errno})")?;
+        }
+
+        // Walk the source chain, indenting each successive link to show the
+        // causal relationship.
+        let mut source = StdError::source(err);
+        let mut indent = 1;
+        while let Some(link) = source {
+            writeln!(f)?;
+            for _ in 0..indent {
+                f.write_str("  ")?;
+            }
+            write!(f, "caused by: {link}")?;
+            source = link.source();
+            indent += 1;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "quic")]
@@ -240,7 +649,7 @@ impl Error {
     ///
     /// This API is currently incomplete and should not be relied upon.
     pub fn alert(&self) -> Option<u8> {
-        match self.0 {
+        match self.context() {
             Context::InvalidInput => None,
             Context::Code(code, _) => {
                 let mut alert = 0;
@@ -268,16 +677,27 @@ impl TryFrom<std::io::Error> for Error {
     type Error = Error;
     fn try_from(value: std::io::Error) -> Result<Self, Self::Error> {
         let io_inner = value.into_inner().ok_or(Error::InvalidInput)?;
-        io_inner
-            .downcast::<Self>()
-            .map(|error| *error)
-            .map_err(|_| Error::InvalidInput)
+        match io_inner.downcast::<Self>() {
+            Ok(error) => Ok(*error),
+            Err(other) => {
+                // The bindings error may sit further down the source chain (for
+                // example wrapped by a higher transport layer), so walk it.
+                let mut source = other.source();
+                while let Some(err) = source {
+                    if let Some(found) = err.downcast_ref::<Self>() {
+                        return Ok(found.clone());
+                    }
+                    source = err.source();
+                }
+                Err(Error::InvalidInput)
+            }
+        }
     }
 }
 
 impl From<Error> for std::io::Error {
     fn from(input: Error) -> Self {
-        if let Context::Code(_, errno) = input.0 {
+        if let Context::Code(_, errno) = input.context() {
             if ErrorType::IOError == input.kind() {
                 let bare = std::io::Error::from_raw_os_error(errno.0);
                 return std::io::Error::new(bare.kind(), input);
@@ -290,7 +710,7 @@ impl From<Error> for std::io::Error {
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut s = f.debug_struct("Error");
-        if let Context::Code(code, _) = self.0 {
+        if let Context::Code(code, _) = self.context() {
             s.field("code", &code);
         }
 
@@ -306,7 +726,7 @@ impl fmt::Debug for Error {
         // "errno" is only known to be meaningful for IOErrors.
         // However, it has occasionally proved useful for debugging
         // other errors, so include it for all errors.
-        if let Context::Code(_, errno) = self.0 {
+        if let Context::Code(_, errno) = self.context() {
             s.field("errno", &errno.to_string());
         }
 
@@ -320,7 +740,20 @@ impl fmt::Display for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        // Note: this is distinct from the inherent `Error::source()`, which
+        // classifies the error as library- vs bindings-originated. This returns
+        // the originating error (e.g. a failed IO callback), if one was attached.
+        match self.repr.data() {
+            ErrorData::Boxed(boxed) => boxed
+                .source
+                .as_ref()
+                .map(|source| source.as_ref() as &(dyn StdError + 'static)),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -391,4 +824,69 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn error_is_one_word() {
+        // The whole point of the bit-packed repr: an inline error stays one
+        // word wide so it's cheap to move through `Poll<Result<..>>`.
+        assert_eq!(
+            core::mem::size_of::<Error>(),
+            core::mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn inline_code_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+        set_errno(Errno(libc::ECONNRESET));
+        unsafe {
+            let s2n_errno_ptr = s2n_errno_location();
+            *s2n_errno_ptr = S2N_IO_ERROR_CODE;
+        }
+
+        let error = FAILURE.into_result().unwrap_err();
+        assert_eq!(error.code(), Some(S2N_IO_ERROR_CODE));
+        assert_eq!(ErrorType::IOError, error.kind());
+
+        // Cloning an inline error preserves its identity.
+        assert_eq!(error, error.clone());
+        Ok(())
+    }
+
+    #[test]
+    fn chain_display_renders_source() -> Result<(), Box<dyn std::error::Error>> {
+        let io_error = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "transport gone");
+        let error = Error::InvalidInput.with_source(io_error);
+
+        let rendered = error.chain_display().to_string();
+        assert!(rendered.contains("InvalidInput"));
+        assert!(rendered.contains("A parameter was incorrect"));
+        assert!(rendered.contains("caused by: transport gone"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn attached_source_chain() -> Result<(), Box<dyn std::error::Error>> {
+        // With no source attached, the std error chain dead-ends.
+        let bare = Error::InvalidInput;
+        assert!(StdError::source(&bare).is_none());
+
+        // An attached IO error is reachable via std::error::Error::source.
+        let io_error = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "transport gone");
+        let error = Error::InvalidInput.with_source(io_error);
+        let source = StdError::source(&error).expect("source should be present");
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+
+        // Converting to an io::Error and back recovers the bindings error,
+        // which here is the io::Error's direct inner error.
+        let wrapped = std::io::Error::new(std::io::ErrorKind::Other, error);
+        let recovered = Error::try_from(wrapped)?;
+        assert_eq!(ErrorType::UsageError, recovered.kind());
+
+        Ok(())
+    }
 }